@@ -3,6 +3,16 @@ use core::fmt;
 use core::ptr::Unique;
 use spin::Mutex;
 use volatile::Volatile;
+#[cfg(all(feature = "hardware_cursor", not(test)))]
+use x86_64::instructions::port::Port;
+
+/// Index register of the VGA CRT controller
+#[cfg(all(feature = "hardware_cursor", not(test)))]
+const CRTC_INDEX_PORT: u16 = 0x3D4;
+
+/// Data register of the VGA CRT controller
+#[cfg(all(feature = "hardware_cursor", not(test)))]
+const CRTC_DATA_PORT: u16 = 0x3D5;
 
 /// Memory address of the VGA buffer
 const VGA_BUFFER_ADDRESS: usize = 0xb8000;
@@ -11,7 +21,7 @@ const VGA_BUFFER_ADDRESS: usize = 0xb8000;
 const BUFFER_LENGTH: usize = 80;
 
 /// Number of lines
-const BUFFER_HEIGHT: usize = 24;
+const BUFFER_HEIGHT: usize = 25;
 
 ///Each printable character contains his own color, and the byte to display
 type Char = (u8, ColorCode);
@@ -63,19 +73,48 @@ impl Writer {
                 self.buffer().content[row][col].write((b' ', color_code));
             }
         }
+        self.update_cursor();
     }
 
     /// Add an empty new line in the buffer
     ///
-    /// Using this method, the `row_position` will move to the next one if
-    /// the current row position is lower than `BUFFER_HEIGHT`.
-    /// Also, we move the `column_position` field to 0.
-    pub fn new_line(&mut self) {
-        self.row_position = (self.row_position + 1) % BUFFER_HEIGHT;
+    /// If the cursor isn't on the last row yet, we simply move `row_position`
+    /// to the next one. Otherwise the screen is full: we scroll the whole
+    /// buffer up by one line (losing the top line) and blank the bottom row,
+    /// keeping `row_position` on the last row.
+    /// In both cases, the `column_position` field is reset to 0.
+    fn new_line(&mut self) {
+        if self.row_position < BUFFER_HEIGHT - 1 {
+            self.row_position += 1;
+        } else {
+            self.scroll();
+        }
         self.column_position = 0;
     }
 
+    /// Shift every line of the buffer up by one row, and blank the last row
+    ///
+    /// This is called by `new_line` once the cursor reaches the last visible
+    /// row, to emulate the standard VGA terminal scrolling behavior.
+    fn scroll(&mut self) {
+        let color_code = self.color_code;
+        for row in 1..BUFFER_HEIGHT {
+            for col in 0..BUFFER_LENGTH {
+                let character = self.buffer().content[row][col].read();
+                self.buffer().content[row - 1][col].write(character);
+            }
+        }
+        for col in 0..BUFFER_LENGTH {
+            self.buffer().content[BUFFER_HEIGHT - 1][col].write((b' ', color_code));
+        }
+    }
+
     /// Write a single byte into the current buffer
+    ///
+    /// Only printable ASCII (`0x20..=0x7e`) and `\n` are written as-is;
+    /// anything else (control characters, or the individual bytes of a
+    /// multi-byte UTF-8 sequence) is substituted with the `0xfe` block
+    /// glyph so `fmt::Write` output stays readable.
     pub fn write_byte(&mut self, byte: u8) {
         let color_code = self.color_code;
         match char::from(byte) {
@@ -83,6 +122,10 @@ impl Writer {
                 self.new_line();
             }
             _ => {
+                let byte = match byte {
+                    0x20..=0x7e => byte,
+                    _ => 0xfe,
+                };
                 if self.column_position >= BUFFER_LENGTH {
                     self.new_line();
                 }
@@ -94,8 +137,40 @@ impl Writer {
                 self.column_position += 1;
             }
         }
+        self.update_cursor();
     }
 
+    /// Move the hardware (blinking) cursor to the current `row_position`/
+    /// `column_position`, so the visible caret tracks the next write.
+    ///
+    /// This programs the VGA CRT controller through its index/data port
+    /// pair: the linear cursor offset is written as two bytes, the high
+    /// byte to register `0x0E` and the low byte to register `0x0F`.
+    /// The ports are only written when the `hardware_cursor` feature is
+    /// enabled, since port I/O requires running on (emulated) x86 hardware;
+    /// otherwise `update_cursor` is a no-op, so the host-side unit tests
+    /// below still build. It is also a no-op under
+    /// `cfg(test)` regardless of the feature flag, since raw `out`
+    /// instructions executed in ring 3 on the host would crash the test
+    /// binary.
+    #[cfg(all(feature = "hardware_cursor", not(test)))]
+    fn update_cursor(&mut self) {
+        let position = (self.row_position * BUFFER_LENGTH + self.column_position) as u16;
+
+        let mut index_port: Port<u8> = Port::new(CRTC_INDEX_PORT);
+        let mut data_port: Port<u8> = Port::new(CRTC_DATA_PORT);
+
+        unsafe {
+            index_port.write(0x0E);
+            data_port.write((position >> 8) as u8);
+            index_port.write(0x0F);
+            data_port.write(position as u8);
+        }
+    }
+
+    #[cfg(any(not(feature = "hardware_cursor"), test))]
+    fn update_cursor(&mut self) {}
+
     /// Returns a mutable reference to the current internal buffer data structure
     fn buffer(&mut self) -> &mut Buffer {
         unsafe { self.buffer.as_mut() }
@@ -151,3 +226,83 @@ macro_rules! clear_screen {
         $crate::buffer::_clear()
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    extern crate array_init;
+
+    use super::*;
+    use core::fmt::Write;
+
+    /// Builds a `Buffer` that lives on the host stack, so `Writer` logic can
+    /// be exercised without touching the real `0xb8000` VGA address.
+    fn test_buffer() -> Buffer {
+        Buffer {
+            content: array_init::array_init(|_| {
+                array_init::array_init(|_| Volatile::new((b' ', ColorCode::default())))
+            }),
+        }
+    }
+
+    /// Builds a `Writer` pointing at the given in-memory `Buffer`.
+    fn test_writer(buffer: &mut Buffer) -> Writer {
+        Writer::new(
+            unsafe { Unique::new_unchecked(buffer) },
+            ColorCode::default(),
+            0,
+            0,
+        )
+    }
+
+    #[test]
+    fn write_str_shorter_than_a_line() {
+        let mut buffer = test_buffer();
+        test_writer(&mut buffer).write_str("hi").unwrap();
+
+        assert_eq!(buffer.content[0][0].read(), (b'h', ColorCode::default()));
+        assert_eq!(buffer.content[0][1].read(), (b'i', ColorCode::default()));
+        assert_eq!(buffer.content[0][2].read(), (b' ', ColorCode::default()));
+    }
+
+    #[test]
+    fn write_str_wraps_past_buffer_length() {
+        let mut buffer = test_buffer();
+        let mut writer = test_writer(&mut buffer);
+
+        for _ in 0..BUFFER_LENGTH {
+            writer.write_str("x").unwrap();
+        }
+        writer.write_str("y").unwrap();
+
+        assert_eq!(buffer.content[0][BUFFER_LENGTH - 1].read().0, b'x');
+        assert_eq!(buffer.content[1][0].read().0, b'y');
+    }
+
+    #[test]
+    fn writing_past_buffer_height_scrolls_the_top_line_off() {
+        let mut buffer = test_buffer();
+        let mut writer = test_writer(&mut buffer);
+
+        for row in 0..BUFFER_HEIGHT {
+            writeln!(writer, "{}", row).unwrap();
+        }
+
+        assert_eq!(buffer.content[0][0].read().0, b'1');
+        assert_eq!(buffer.content[BUFFER_HEIGHT - 1][0].read(), (b' ', ColorCode::default()));
+    }
+
+    #[test]
+    fn clear_fills_the_buffer_with_spaces_in_the_active_color() {
+        let mut buffer = test_buffer();
+        let mut writer = test_writer(&mut buffer);
+
+        writer.write_str("hello").unwrap();
+        writer.clear();
+
+        for row in 0..BUFFER_HEIGHT {
+            for col in 0..BUFFER_LENGTH {
+                assert_eq!(buffer.content[row][col].read(), (b' ', ColorCode::default()));
+            }
+        }
+    }
+}
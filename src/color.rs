@@ -2,6 +2,7 @@
  * Represents a VGA's 16-color-modes.
  * Each enum is an hexadecimal representation of the given color.
  */
+#[derive(Clone, Copy)]
 pub enum Color {
     Black = 0x0,
     Blue = 0x1,
@@ -25,7 +26,7 @@ pub enum Color {
  * Represents, as a byte, the background color (4 first bits) and the foreground
  * color (4 last bits) of a printable character.
  */
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct ColorCode(u8);
 
 impl ColorCode {
@@ -36,6 +37,42 @@ impl ColorCode {
     const fn new(foreground: Color, background: Color) -> ColorCode {
         ColorCode((background as u8) << 4 | (foreground as u8))
     }
+
+    /*
+     * Returns a new ColorCode, validating that a "light" background
+     * (0x8..=0xF) is only requested when blink is disabled.
+     * On real/emulated VGA hardware, enabling blink steals the high bit of
+     * the background nibble to request a blinking character, leaving only
+     * the 8 low background colors (0x0..=0x7) representable.
+     */
+    pub fn with_background_mode(foreground: Color, background: Color, blink: bool) -> ColorCode {
+        assert!(
+            !blink || (background as u8) <= 0x7,
+            "light backgrounds (0x8..=0xF) require blink to be disabled"
+        );
+        let code = ColorCode::new(foreground, background);
+        if blink {
+            code.with_blink(true)
+        } else {
+            code
+        }
+    }
+
+    /*
+     * Sets or clears the blink bit of the attribute byte.
+     * Enabling it makes the character blink, but restricts the background
+     * to the 8 low colors (0x0..=0x7). Disabling it just clears the bit,
+     * which also zeroes the background's high bit, so it should only be
+     * called on a ColorCode whose background already fits in 0x0..=0x7,
+     * or not called at all if the background needs the full 16 colors.
+     */
+    pub const fn with_blink(self, blink: bool) -> ColorCode {
+        if blink {
+            ColorCode(self.0 | 0x80)
+        } else {
+            ColorCode(self.0 & !0x80)
+        }
+    }
 }
 
 impl Default for ColorCode {
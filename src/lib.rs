@@ -1,11 +1,15 @@
-#![feature(const_fn)]
 #![feature(ptr_internals)]
-#![no_std]
+#![allow(internal_features)]
+#![cfg_attr(not(test), no_std)]
 
+#[cfg(test)]
+extern crate core;
 extern crate spin;
 extern crate volatile;
 #[macro_use]
 extern crate lazy_static;
+#[cfg(feature = "hardware_cursor")]
+extern crate x86_64;
 
 pub mod buffer;
 pub mod color;